@@ -1,12 +1,16 @@
 use std::env;
 
 use crate::error::ContractError;
-use crate::msg::{ArbiterResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{config, config_read, State};
+use crate::msg::{
+    ArbiterResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, FundersResponse, FundsResponse,
+    InstantiateMsg, PendingApprovalResponse, QueryMsg,
+};
+use crate::state::{config, config_read, Approval, State};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::{
-    entry_point, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult,
+    entry_point, from_binary, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env,
+    MessageInfo, Response, StdResult, Uint128, WasmMsg,
 };
 
 // version info for migration info
@@ -26,6 +30,15 @@ pub fn instantiate(
         source: info.sender,
         end_height: msg.end_height,
         end_time: msg.end_time,
+        cw20_tokens: vec![],
+        schedule: msg.schedule,
+        approved_amount: vec![],
+        claimed_amount: vec![],
+        goal: msg.goal,
+        funders: vec![],
+        require_dual_approval: msg.require_dual_approval,
+        arbiter_approval: None,
+        source_approval: None,
     };
     if state.is_expired(&env) {
         return Err(ContractError::Expired {
@@ -49,44 +62,284 @@ pub fn execute(
     match msg {
         ExecuteMsg::Approve { quantity } => try_approve(deps, env, info, state, quantity),
         ExecuteMsg::Refund {} => try_refund(deps, info, env, state),
+        ExecuteMsg::Receive(msg) => try_receive(deps, info, state, msg),
+        ExecuteMsg::Claim {} => try_claim(deps, env, info, state),
+        ExecuteMsg::Fund {} => try_fund(deps, env, info, state),
+        ExecuteMsg::Split {
+            to_recipient,
+            to_source,
+        } => try_split(deps, env, info, state, to_recipient, to_source),
     }
 }
 
-pub fn try_approve(
+pub fn try_split(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     state: State,
-    quantity: Option<Vec<Coin>>,
+    to_recipient: Vec<Coin>,
+    to_source: Vec<Coin>,
 ) -> Result<Response, ContractError> {
+    if info.sender != state.arbiter {
+        return Err(ContractError::Unauthorized {});
+    }
+    if state.goal.is_some() {
+        return Err(ContractError::SplitUnavailableForCrowdfunding {});
+    }
+    if state.require_dual_approval {
+        return Err(ContractError::SplitRequiresDualApproval {});
+    }
+    if state.schedule.is_some() {
+        return Err(ContractError::SplitUnavailableWithSchedule {});
+    }
     if state.is_expired(&env) {
         return Err(ContractError::Expired {
             end_height: state.end_height,
             end_time: state.end_time,
         });
     }
-    if info.sender != state.arbiter {
+    let balance = deps.querier.query_all_balances(&env.contract.address)?;
+    for requested in to_recipient.iter().chain(to_source.iter()) {
+        let total_requested: Uint128 = to_recipient
+            .iter()
+            .chain(to_source.iter())
+            .filter(|c| c.denom == requested.denom)
+            .map(|c| c.amount)
+            .sum();
+        let available = balance
+            .iter()
+            .find(|c| c.denom == requested.denom)
+            .map(|c| c.amount)
+            .unwrap_or_default();
+        if total_requested > available {
+            return Err(ContractError::InsufficientFunds {
+                denom: requested.denom.clone(),
+            });
+        }
+    }
+
+    let res = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: state.recipient.into_string(),
+            amount: to_recipient,
+        })
+        .add_message(BankMsg::Send {
+            to_address: state.source.into_string(),
+            amount: to_source,
+        })
+        .add_attribute("action", "split");
+    Ok(res)
+}
+
+pub fn try_fund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut state: State,
+) -> Result<Response, ContractError> {
+    let goal = state.goal.clone().ok_or(ContractError::NotCrowdfunding {})?;
+    if state.is_expired(&env) {
+        return Err(ContractError::Expired {
+            end_height: state.end_height,
+            end_time: state.end_time,
+        });
+    }
+    if let Some(other) = info.funds.iter().find(|c| c.denom != goal.denom) {
+        return Err(ContractError::UnsupportedFundingDenom {
+            denom: other.denom.clone(),
+            goal_denom: goal.denom,
+        });
+    }
+    let sent = info
+        .funds
+        .iter()
+        .find(|c| c.denom == goal.denom)
+        .map(|c| c.amount)
+        .unwrap_or_default();
+    if sent.is_zero() {
+        return Err(ContractError::NoFundsSent {});
+    }
+    state.add_funder(info.sender, sent);
+    config(deps.storage).save(&state)?;
+    Ok(Response::new().add_attribute("action", "fund"))
+}
+
+pub fn try_receive(
+    deps: DepsMut,
+    info: MessageInfo,
+    mut state: State,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let token_address = info.sender;
+    match from_binary(&wrapper.msg)? {
+        Cw20HookMsg::Deposit {} => {
+            if state.schedule.is_some() {
+                return Err(ContractError::Cw20UnsupportedWithSchedule {});
+            }
+            if state.goal.is_some() {
+                return Err(ContractError::Cw20UnsupportedForCrowdfunding {});
+            }
+            state.add_cw20_token(token_address, wrapper.amount);
+            config(deps.storage).save(&state)?;
+            Ok(Response::new().add_attribute("action", "deposit_cw20"))
+        }
+    }
+}
+
+pub fn try_approve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut state: State,
+    quantity: Option<Vec<Coin>>,
+) -> Result<Response, ContractError> {
+    let is_arbiter = info.sender == state.arbiter;
+    let is_source = info.sender == state.source;
+    if state.require_dual_approval {
+        if !is_arbiter && !is_source {
+            return Err(ContractError::Unauthorized {});
+        }
+    } else if !is_arbiter {
         return Err(ContractError::Unauthorized {});
     }
+    if let Some(goal) = state.goal.clone() {
+        if !state.is_expired(&env) {
+            return Err(ContractError::NotExpired {
+                end_height: state.end_height,
+                end_time: state.end_time,
+            });
+        }
+        if state.total_funded() < goal.amount {
+            return Err(ContractError::GoalNotMet {});
+        }
+    } else if state.is_expired(&env) {
+        return Err(ContractError::Expired {
+            end_height: state.end_height,
+            end_time: state.end_time,
+        });
+    }
+
+    if state.require_dual_approval {
+        let other_existing = if is_arbiter {
+            state.source_approval.clone()
+        } else {
+            state.arbiter_approval.clone()
+        };
+        match other_existing {
+            Some(other) if other.quantity == quantity => {
+                state.arbiter_approval = None;
+                state.source_approval = None;
+            }
+            Some(_) => return Err(ContractError::ApprovalMismatch {}),
+            None => {
+                let approval = Approval {
+                    quantity: quantity.clone(),
+                };
+                if is_arbiter {
+                    state.arbiter_approval = Some(approval);
+                } else {
+                    state.source_approval = Some(approval);
+                }
+                config(deps.storage).save(&state)?;
+                return Ok(Response::new().add_attribute("action", "approve_pending"));
+            }
+        }
+    }
+
     let amount = if let Some(quantity) = quantity {
         quantity
     } else {
         deps.querier.query_all_balances(&env.contract.address)?
     };
-    let res = Response::new()
+    let cw20_messages = cw20_transfer_messages(&state, &state.recipient)?;
+    state.cw20_tokens = vec![];
+    if state.goal.is_some() {
+        state.funders = vec![];
+    }
+
+    // With a vesting schedule, the native amount is only recorded as
+    // approved; the recipient unlocks it over time via `Claim`.
+    let mut res = Response::new();
+    if state.schedule.is_some() {
+        state.approved_amount = amount;
+        res = res.add_attribute("Approved", "pending_schedule");
+    } else {
+        res = res
+            .add_message(BankMsg::Send {
+                to_address: state.recipient.clone().into_string(),
+                amount,
+            })
+            .add_attribute("Approved", "amount");
+    }
+    res = res.add_messages(cw20_messages);
+    config(deps.storage).save(&state)?;
+    Ok(res)
+}
+
+pub fn try_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut state: State,
+) -> Result<Response, ContractError> {
+    if info.sender != state.recipient {
+        return Err(ContractError::Unauthorized {});
+    }
+    let schedule = state.schedule.clone().ok_or(ContractError::NoReleaseSchedule {})?;
+    let now = env.block.time.seconds();
+    if now < schedule.start_time + schedule.cliff {
+        return Err(ContractError::CliffNotReached {});
+    }
+
+    let vested = state.vested_amount(&schedule, now);
+    let to_send = state.claimable_amount(&vested);
+    if to_send.is_empty() {
+        return Ok(Response::new().add_attribute("action", "claim_nothing"));
+    }
+
+    state.claimed_amount = vested;
+    config(deps.storage).save(&state)?;
+
+    Ok(Response::new()
         .add_message(BankMsg::Send {
             to_address: state.recipient.into_string(),
-            amount,
+            amount: to_send,
         })
-        .add_attribute("Approved", "amount");
-    Ok(res)
+        .add_attribute("action", "claim"))
 }
 pub fn try_refund(
     deps: DepsMut,
     info: MessageInfo,
     env: Env,
-    state: State,
+    mut state: State,
 ) -> Result<Response, ContractError> {
+    if let Some(goal) = state.goal.clone() {
+        if !state.is_expired(&env) {
+            return Err(ContractError::NotExpired {
+                end_height: state.end_height,
+                end_time: state.end_time,
+            });
+        }
+        if state.total_funded() >= goal.amount {
+            return Err(ContractError::GoalAlreadyMet {});
+        }
+        let messages: Vec<BankMsg> = state
+            .funders
+            .iter()
+            .map(|funder| BankMsg::Send {
+                to_address: funder.address.to_string(),
+                amount: vec![Coin {
+                    denom: goal.denom.clone(),
+                    amount: funder.amount,
+                }],
+            })
+            .collect();
+        state.funders = vec![];
+        config(deps.storage).save(&state)?;
+        return Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "refund_crowdfund"));
+    }
     if info.sender != state.arbiter {
         return Err(ContractError::Unauthorized {});
     }
@@ -98,18 +351,44 @@ pub fn try_refund(
     }
 
     let amount = deps.querier.query_all_balances(&env.contract.address)?;
+    let cw20_messages = cw20_transfer_messages(&state, &state.source)?;
+    state.cw20_tokens = vec![];
+    config(deps.storage).save(&state)?;
 
-    let res = Response::new().add_message(BankMsg::Send {
-        to_address: state.source.into_string(),
-        amount,
-    });
+    let res = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: state.source.into_string(),
+            amount,
+        })
+        .add_messages(cw20_messages);
     Ok(res)
 }
 
+fn cw20_transfer_messages(state: &State, to: &Addr) -> StdResult<Vec<WasmMsg>> {
+    state
+        .cw20_tokens
+        .iter()
+        .map(|token| {
+            Ok(WasmMsg::Execute {
+                contract_addr: token.address.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: to.to_string(),
+                    amount: token.amount,
+                })?,
+                funds: vec![],
+            })
+        })
+        .collect()
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Arbiter {} => to_binary(&query_arbiter(deps)?),
+        QueryMsg::Funders {} => to_binary(&query_funders(deps)?),
+        QueryMsg::Funds {} => to_binary(&query_funds(deps)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingApproval {} => to_binary(&query_pending_approval(deps)?),
     }
 }
 
@@ -119,6 +398,42 @@ fn query_arbiter(deps: Deps) -> StdResult<ArbiterResponse> {
     Ok(ArbiterResponse { arbiter: addr })
 }
 
+fn query_funders(deps: Deps) -> StdResult<FundersResponse> {
+    let state = config_read(deps.storage).load()?;
+    Ok(FundersResponse {
+        funders: state
+            .funders
+            .into_iter()
+            .map(|f| (f.address, f.amount))
+            .collect(),
+    })
+}
+
+fn query_funds(deps: Deps) -> StdResult<FundsResponse> {
+    let state = config_read(deps.storage).load()?;
+    Ok(FundsResponse {
+        total: state.total_funded(),
+    })
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let state = config_read(deps.storage).load()?;
+    Ok(ConfigResponse {
+        goal: state.goal.clone(),
+        end_height: state.end_height,
+        end_time: state.end_time,
+        total_raised: state.total_funded(),
+    })
+}
+
+fn query_pending_approval(deps: Deps) -> StdResult<PendingApprovalResponse> {
+    let state = config_read(deps.storage).load()?;
+    Ok(PendingApprovalResponse {
+        arbiter_approved: state.arbiter_approval.is_some(),
+        source_approved: state.source_approval.is_some(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::iter::Inspect;
@@ -129,7 +444,8 @@ mod tests {
     use cosmwasm_std::testing::{
         mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info,
     };
-    use cosmwasm_std::{coin, coins, from_binary, CosmosMsg, Timestamp};
+    use cosmwasm_std::{coin, coins, from_binary, to_binary, CosmosMsg, Timestamp, Uint128};
+    use cw20::Cw20ReceiveMsg;
 
     fn init_msg_expire_by_height(height: u64) -> InstantiateMsg {
         InstantiateMsg {
@@ -137,6 +453,9 @@ mod tests {
             recipient: String::from("benefits"),
             end_height: Some(height),
             end_time: None,
+            schedule: None,
+            goal: None,
+            require_dual_approval: false,
         }
     }
 
@@ -162,7 +481,16 @@ mod tests {
                 recipient: Addr::unchecked("benefits"),
                 source: Addr::unchecked("creator"),
                 end_height: Some(1000),
-                end_time: None
+                end_time: None,
+                cw20_tokens: vec![],
+                schedule: None,
+                approved_amount: vec![],
+                claimed_amount: vec![],
+                goal: None,
+                funders: vec![],
+                require_dual_approval: false,
+                arbiter_approval: None,
+                source_approval: None,
             }
         );
     }
@@ -196,6 +524,9 @@ mod tests {
             recipient: recipient.into(),
             end_height: None,
             end_time: None,
+            schedule: None,
+            goal: None,
+            require_dual_approval: false,
         };
         let mut env = mock_env();
         env.block.height = 978;
@@ -345,4 +676,522 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn deposit_and_approve_cw20() {
+        let mut deps = mock_dependencies();
+        let msg = init_msg_expire_by_height(1000);
+        let mut env = mock_env();
+        env.block.height = 900;
+        env.block.time = Timestamp::from_seconds(0);
+
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "creator".into(),
+            amount: Uint128::new(500),
+            msg: to_binary(&msg::Cw20HookMsg::Deposit {}).unwrap(),
+        });
+        let info = mock_info("token-contract", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, receive).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let state = config_read(&mut deps.storage).load().unwrap();
+        assert_eq!(state.cw20_tokens.len(), 1);
+        assert_eq!(state.cw20_tokens[0].address, Addr::unchecked("token-contract"));
+        assert_eq!(state.cw20_tokens[0].amount, Uint128::new(500));
+
+        let approve = ExecuteMsg::Approve { quantity: None };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), env, info, approve).unwrap();
+        assert_eq!(2, res.messages.len());
+        let cw20_msg = res.messages.get(1).expect("no cw20 message");
+        assert_eq!(
+            cw20_msg.msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token-contract".into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "benefits".into(),
+                    amount: Uint128::new(500),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn claim_vests_linearly() {
+        let mut deps = mock_dependencies_with_balance(&coins(1000, "earth"));
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: None,
+            end_time: None,
+            schedule: Some(crate::state::Schedule {
+                start_time: 1000,
+                cliff: 100,
+                duration: 1000,
+            }),
+            goal: None,
+            require_dual_approval: false,
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &coins(1000, "earth"));
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // approve just records the amount, it does not release funds yet
+        let approve = ExecuteMsg::Approve { quantity: None };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, approve).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // recipient cannot claim before the cliff
+        env.block.time = Timestamp::from_seconds(1050);
+        let claim = ExecuteMsg::Claim {};
+        let info = mock_info("benefits", &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, claim.clone()).unwrap_err();
+        match err {
+            ContractError::CliffNotReached {} => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+
+        // halfway through the schedule, half of the approved amount vests
+        env.block.time = Timestamp::from_seconds(1500);
+        let info = mock_info("benefits", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, claim.clone()).unwrap();
+        assert_eq!(1, res.messages.len());
+        let msg = res.messages.get(0).expect("no message");
+        assert_eq!(
+            msg.msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(500, "earth")
+            })
+        );
+
+        // a second claim right away has nothing new to send
+        let info = mock_info("benefits", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, claim.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // after the full duration, the remainder vests
+        env.block.time = Timestamp::from_seconds(2000);
+        let info = mock_info("benefits", &[]);
+        let res = execute(deps.as_mut(), env, info, claim).unwrap();
+        assert_eq!(1, res.messages.len());
+        let msg = res.messages.get(0).expect("no message");
+        assert_eq!(
+            msg.msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(500, "earth")
+            })
+        );
+    }
+
+    #[test]
+    fn crowdfunding_goal_met_then_approved() {
+        let mut deps = mock_dependencies_with_balance(&coins(1000, "earth"));
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: Some(1000),
+            end_time: None,
+            schedule: None,
+            goal: Some(coin(1000, "earth")),
+            require_dual_approval: false,
+        };
+        let mut env = mock_env();
+        env.block.height = 500;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let fund = ExecuteMsg::Fund {};
+        let info = mock_info("alice", &coins(600, "earth"));
+        execute(deps.as_mut(), env.clone(), info, fund.clone()).unwrap();
+        let info = mock_info("bob", &coins(400, "earth"));
+        execute(deps.as_mut(), env.clone(), info, fund).unwrap();
+
+        let funds = query_funds(deps.as_ref()).unwrap();
+        assert_eq!(funds.total, Uint128::new(1000));
+
+        // cannot approve before the deadline, even though the goal is met
+        let approve = ExecuteMsg::Approve { quantity: None };
+        let info = mock_info("verifies", &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, approve.clone()).unwrap_err();
+        match err {
+            ContractError::NotExpired { .. } => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+
+        env.block.height = 1001;
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), env, info, approve).unwrap();
+        assert_eq!(1, res.messages.len());
+        let msg = res.messages.get(0).expect("no message");
+        assert_eq!(
+            msg.msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(1000, "earth")
+            })
+        );
+
+        // settlement clears the contribution ledger, not just the balance
+        let funds = query_funds(deps.as_ref()).unwrap();
+        assert_eq!(funds.total, Uint128::zero());
+        let funders = query_funders(deps.as_ref()).unwrap();
+        assert!(funders.funders.is_empty());
+    }
+
+    #[test]
+    fn fund_rejects_off_goal_denom() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: Some(1000),
+            end_time: None,
+            schedule: None,
+            goal: Some(coin(1000, "earth")),
+            require_dual_approval: false,
+        };
+        let mut env = mock_env();
+        env.block.height = 500;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let fund = ExecuteMsg::Fund {};
+        let info = mock_info("alice", &coins(600, "moon"));
+        let err = execute(deps.as_mut(), env, info, fund).unwrap_err();
+        match err {
+            ContractError::UnsupportedFundingDenom { .. } => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn crowdfunding_goal_missed_refunds_each_funder() {
+        let mut deps = mock_dependencies_with_balance(&coins(600, "earth"));
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: Some(1000),
+            end_time: None,
+            schedule: None,
+            goal: Some(coin(1000, "earth")),
+            require_dual_approval: false,
+        };
+        let mut env = mock_env();
+        env.block.height = 500;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let fund = ExecuteMsg::Fund {};
+        let info = mock_info("alice", &coins(600, "earth"));
+        execute(deps.as_mut(), env.clone(), info, fund).unwrap();
+
+        // too early, and below goal
+        let refund = ExecuteMsg::Refund {};
+        let info = mock_info("anyone", &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, refund.clone()).unwrap_err();
+        match err {
+            ContractError::NotExpired { .. } => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+
+        env.block.height = 1001;
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), env, info, refund).unwrap();
+        assert_eq!(1, res.messages.len());
+        let msg = res.messages.get(0).expect("no message");
+        assert_eq!(
+            msg.msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(600, "earth")
+            })
+        );
+    }
+
+    #[test]
+    fn split_allocates_between_recipient_and_source() {
+        let mut deps = mock_dependencies_with_balance(&coins(1000, "earth"));
+        let msg = init_msg_expire_by_height(1000);
+        let mut env = mock_env();
+        env.block.height = 900;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &coins(1000, "earth"));
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let split = ExecuteMsg::Split {
+            to_recipient: coins(600, "earth"),
+            to_source: coins(400, "earth"),
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), env, info, split).unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages.get(0).unwrap().msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(600, "earth")
+            })
+        );
+        assert_eq!(
+            res.messages.get(1).unwrap().msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(400, "earth")
+            })
+        );
+    }
+
+    #[test]
+    fn split_rejects_overdrawn_denom() {
+        let mut deps = mock_dependencies_with_balance(&coins(1000, "earth"));
+        let msg = init_msg_expire_by_height(1000);
+        let mut env = mock_env();
+        env.block.height = 900;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &coins(1000, "earth"));
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let split = ExecuteMsg::Split {
+            to_recipient: coins(600, "earth"),
+            to_source: coins(500, "earth"),
+        };
+        let info = mock_info("verifies", &[]);
+        let err = execute(deps.as_mut(), env, info, split).unwrap_err();
+        match err {
+            ContractError::InsufficientFunds { .. } => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn split_rejected_for_crowdfunding() {
+        let mut deps = mock_dependencies_with_balance(&coins(400, "earth"));
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: Some(1000),
+            end_time: None,
+            schedule: None,
+            goal: Some(coin(1000, "earth")),
+            require_dual_approval: false,
+        };
+        let mut env = mock_env();
+        env.block.height = 500;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let fund = ExecuteMsg::Fund {};
+        let info = mock_info("alice", &coins(400, "earth"));
+        execute(deps.as_mut(), env.clone(), info, fund).unwrap();
+
+        let split = ExecuteMsg::Split {
+            to_recipient: coins(400, "earth"),
+            to_source: vec![],
+        };
+        let info = mock_info("verifies", &[]);
+        let err = execute(deps.as_mut(), env, info, split).unwrap_err();
+        match err {
+            ContractError::SplitUnavailableForCrowdfunding {} => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn dual_approval_requires_both_parties_to_match() {
+        let mut deps = mock_dependencies_with_balance(&coins(1000, "earth"));
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: Some(1000),
+            end_time: None,
+            schedule: None,
+            goal: None,
+            require_dual_approval: true,
+        };
+        let mut env = mock_env();
+        env.block.height = 900;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &coins(1000, "earth"));
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // arbiter approves first: only recorded, nothing released yet
+        let approve = ExecuteMsg::Approve { quantity: None };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, approve.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let pending = query_pending_approval(deps.as_ref()).unwrap();
+        assert!(pending.arbiter_approved);
+        assert!(!pending.source_approved);
+
+        // source submits a mismatched quantity: rejected
+        let mismatched = ExecuteMsg::Approve {
+            quantity: Some(coins(500, "earth")),
+        };
+        let info = mock_info("creator", &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, mismatched).unwrap_err();
+        match err {
+            ContractError::ApprovalMismatch {} => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+
+        // source submits the matching quantity: funds release
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), env, info, approve).unwrap();
+        assert_eq!(1, res.messages.len());
+        let msg = res.messages.get(0).expect("no message");
+        assert_eq!(
+            msg.msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(1000, "earth")
+            })
+        );
+
+        let pending = query_pending_approval(deps.as_ref()).unwrap();
+        assert!(!pending.arbiter_approved);
+        assert!(!pending.source_approved);
+    }
+
+    #[test]
+    fn split_rejected_with_schedule() {
+        let mut deps = mock_dependencies_with_balance(&coins(1000, "earth"));
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: Some(1000),
+            end_time: None,
+            schedule: Some(crate::state::Schedule {
+                start_time: 1000,
+                cliff: 100,
+                duration: 1000,
+            }),
+            goal: None,
+            require_dual_approval: false,
+        };
+        let mut env = mock_env();
+        env.block.height = 900;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &coins(1000, "earth"));
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let split = ExecuteMsg::Split {
+            to_recipient: coins(1000, "earth"),
+            to_source: vec![],
+        };
+        let info = mock_info("verifies", &[]);
+        let err = execute(deps.as_mut(), env, info, split).unwrap_err();
+        match err {
+            ContractError::SplitUnavailableWithSchedule {} => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn split_rejected_under_dual_approval() {
+        let mut deps = mock_dependencies_with_balance(&coins(1000, "earth"));
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: Some(1000),
+            end_time: None,
+            schedule: None,
+            goal: None,
+            require_dual_approval: true,
+        };
+        let mut env = mock_env();
+        env.block.height = 900;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &coins(1000, "earth"));
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let split = ExecuteMsg::Split {
+            to_recipient: coins(1000, "earth"),
+            to_source: vec![],
+        };
+        let info = mock_info("verifies", &[]);
+        let err = execute(deps.as_mut(), env, info, split).unwrap_err();
+        match err {
+            ContractError::SplitRequiresDualApproval {} => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn cw20_deposit_rejected_for_crowdfunding() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: Some(1000),
+            end_time: None,
+            schedule: None,
+            goal: Some(coin(1000, "earth")),
+            require_dual_approval: false,
+        };
+        let mut env = mock_env();
+        env.block.height = 500;
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "alice".into(),
+            amount: Uint128::new(500),
+            msg: to_binary(&msg::Cw20HookMsg::Deposit {}).unwrap(),
+        });
+        let info = mock_info("token-contract", &[]);
+        let err = execute(deps.as_mut(), env, info, receive).unwrap_err();
+        match err {
+            ContractError::Cw20UnsupportedForCrowdfunding {} => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn cw20_deposit_rejected_with_schedule() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            arbiter: String::from("verifies"),
+            recipient: String::from("benefits"),
+            end_height: None,
+            end_time: None,
+            schedule: Some(crate::state::Schedule {
+                start_time: 1000,
+                cliff: 100,
+                duration: 1000,
+            }),
+            goal: None,
+            require_dual_approval: false,
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "creator".into(),
+            amount: Uint128::new(500),
+            msg: to_binary(&msg::Cw20HookMsg::Deposit {}).unwrap(),
+        });
+        let info = mock_info("token-contract", &[]);
+        let err = execute(deps.as_mut(), env, info, receive).unwrap_err();
+        match err {
+            ContractError::Cw20UnsupportedWithSchedule {} => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+    }
 }
@@ -19,6 +19,49 @@ pub enum ContractError {
     NotExpired {
         end_height: Option<u64>,
         end_time: Option<u64>,
-    }, // Add any other custom errors you like here.
-       // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
+    },
+
+    #[error("No vesting schedule configured for this escrow")]
+    NoReleaseSchedule {},
+
+    #[error("Cliff period has not passed yet")]
+    CliffNotReached {},
+
+    #[error("This escrow is not in crowdfunding mode")]
+    NotCrowdfunding {},
+
+    #[error("No funds of the goal's denom were sent")]
+    NoFundsSent {},
+
+    #[error("The funding goal has not been reached yet")]
+    GoalNotMet {},
+
+    #[error("The funding goal has already been reached, use Approve instead")]
+    GoalAlreadyMet {},
+
+    #[error("Insufficient funds: requested more {denom} than the contract holds")]
+    InsufficientFunds { denom: String },
+
+    #[error("Quantity does not match the other party's pending approval")]
+    ApprovalMismatch {},
+
+    #[error("CW20 deposits are not supported alongside a vesting schedule")]
+    Cw20UnsupportedWithSchedule {},
+
+    #[error("CW20 deposits are not supported for crowdfunding-mode escrows")]
+    Cw20UnsupportedForCrowdfunding {},
+
+    #[error("Split is not available for crowdfunding-mode escrows; use Approve or Refund instead")]
+    SplitUnavailableForCrowdfunding {},
+
+    #[error("Split is not available when require_dual_approval is set; use Approve instead")]
+    SplitRequiresDualApproval {},
+
+    #[error("Split is not available alongside a vesting schedule; use Approve and Claim instead")]
+    SplitUnavailableWithSchedule {},
+
+    #[error("Only the goal's denom ({goal_denom}) can be sent to Fund; got {denom}")]
+    UnsupportedFundingDenom { denom: String, goal_denom: String },
+    // Add any other custom errors you like here.
+    // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -0,0 +1,104 @@
+use cosmwasm_std::{Addr, Coin, Uint128};
+use cw20::Cw20ReceiveMsg;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::Schedule;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub arbiter: String,
+    pub recipient: String,
+    pub end_height: Option<u64>,
+    pub end_time: Option<u64>,
+    /// Optional linear vesting schedule gating how approved funds unlock to
+    /// the recipient. When absent, `Approve` releases funds immediately.
+    pub schedule: Option<Schedule>,
+    /// Switches the escrow into crowdfunding mode: many funders pool native
+    /// tokens towards this goal instead of a single up-front `source`
+    /// deposit. `Approve` then requires the goal to be met by the deadline;
+    /// missing it makes `Refund` pay each funder back individually.
+    pub goal: Option<Coin>,
+    /// When true, a release to the recipient requires matching `Approve`
+    /// calls from both the arbiter and the source (see `ExecuteMsg::Approve`)
+    /// instead of the arbiter acting alone.
+    #[serde(default)]
+    pub require_dual_approval: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Release `quantity` (or the whole balance) to the recipient. Under
+    /// `require_dual_approval`, the first matching call from the arbiter or
+    /// the source is only recorded as pending; the release happens once the
+    /// other side submits an `Approve` with the same `quantity`.
+    Approve { quantity: Option<Vec<Coin>> },
+    Refund {},
+    Receive(Cw20ReceiveMsg),
+    /// Claim the portion of an approved, scheduled release that has vested
+    /// so far. Only valid once a `Schedule` is configured.
+    Claim {},
+    /// Contribute native funds towards the `goal` of a crowdfunding-mode
+    /// escrow. Only valid once a `goal` is configured.
+    Fund {},
+    /// Resolve a dispute by allocating part of the escrowed native balance
+    /// to the recipient and part back to the source in a single call,
+    /// instead of releasing or refunding everything.
+    Split {
+        to_recipient: Vec<Coin>,
+        to_source: Vec<Coin>,
+    },
+}
+
+/// Sub-message encoded in the `msg` field of a `Cw20ReceiveMsg` sent to this
+/// contract, i.e. the payload of a `Cw20ExecuteMsg::Send` to fund the escrow.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    Deposit {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Arbiter {},
+    /// The recorded contribution of each funder, for a crowdfunding-mode
+    /// escrow.
+    Funders {},
+    /// The total amount raised so far, for a crowdfunding-mode escrow.
+    Funds {},
+    /// The goal and deadline of a crowdfunding-mode escrow.
+    Config {},
+    /// Who has submitted an `Approve` so far under `require_dual_approval`.
+    PendingApproval {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ArbiterResponse {
+    pub arbiter: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FundersResponse {
+    pub funders: Vec<(Addr, Uint128)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FundsResponse {
+    pub total: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub goal: Option<Coin>,
+    pub end_height: Option<u64>,
+    pub end_time: Option<u64>,
+    pub total_raised: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingApprovalResponse {
+    pub arbiter_approved: bool,
+    pub source_approved: bool,
+}
@@ -0,0 +1,152 @@
+use cosmwasm_std::{Addr, Coin, Env, Timestamp, Uint128};
+use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub static CONFIG_KEY: &[u8] = b"config";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20Coin {
+    pub address: Addr,
+    pub amount: Uint128,
+}
+
+/// A linear vesting schedule for funds the arbiter has approved for release.
+/// No funds vest before `start_time + cliff`; all of them have vested by
+/// `start_time + duration`, growing linearly in between.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Schedule {
+    pub start_time: u64,
+    pub cliff: u64,
+    pub duration: u64,
+}
+
+/// A single contribution recorded against a crowdfunding-mode escrow's
+/// `goal`. `amount` is always denominated in `State::goal`'s denom.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Funder {
+    pub address: Addr,
+    pub amount: Uint128,
+}
+
+/// One side's submitted `Approve` under `require_dual_approval`, recording
+/// the quantity it expects to release so it can be matched against the
+/// other side's submission.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Approval {
+    pub quantity: Option<Vec<Coin>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct State {
+    pub arbiter: Addr,
+    pub recipient: Addr,
+    pub source: Addr,
+    pub end_height: Option<u64>,
+    pub end_time: Option<u64>,
+    pub cw20_tokens: Vec<Cw20Coin>,
+    pub schedule: Option<Schedule>,
+    pub approved_amount: Vec<Coin>,
+    pub claimed_amount: Vec<Coin>,
+    /// When set, this escrow is in crowdfunding mode: many funders pool
+    /// native tokens towards `goal` instead of a single `source` depositing
+    /// up front.
+    pub goal: Option<Coin>,
+    pub funders: Vec<Funder>,
+    /// When set, a release to the recipient requires matching `Approve`
+    /// calls from both the arbiter and the source instead of the arbiter
+    /// acting unilaterally.
+    pub require_dual_approval: bool,
+    pub arbiter_approval: Option<Approval>,
+    pub source_approval: Option<Approval>,
+}
+
+impl State {
+    pub fn is_expired(&self, env: &Env) -> bool {
+        if let Some(end_height) = self.end_height {
+            if env.block.height > end_height {
+                return true;
+            }
+        }
+        if let Some(end_time) = self.end_time {
+            if env.block.time > Timestamp::from_seconds(end_time) {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn add_cw20_token(&mut self, address: Addr, amount: Uint128) {
+        match self.cw20_tokens.iter_mut().find(|t| t.address == address) {
+            Some(token) => token.amount += amount,
+            None => self.cw20_tokens.push(Cw20Coin { address, amount }),
+        }
+    }
+
+    /// The amount of `approved_amount` vested to the recipient at time `now`,
+    /// per the linear schedule described in [`Schedule`].
+    pub fn vested_amount(&self, schedule: &Schedule, now: u64) -> Vec<Coin> {
+        if now < schedule.start_time + schedule.cliff {
+            return vec![];
+        }
+        if now >= schedule.start_time + schedule.duration {
+            return self.approved_amount.clone();
+        }
+        let elapsed = now - schedule.start_time;
+        self.approved_amount
+            .iter()
+            .map(|coin| Coin {
+                denom: coin.denom.clone(),
+                amount: coin.amount.multiply_ratio(elapsed, schedule.duration),
+            })
+            .collect()
+    }
+
+    /// The newly-vested delta that has not yet been claimed, per denom.
+    /// Denoms where `claimed_amount >= vested_amount` are omitted rather than
+    /// underflowing.
+    pub fn claimable_amount(&self, vested: &[Coin]) -> Vec<Coin> {
+        vested
+            .iter()
+            .filter_map(|coin| {
+                let already_claimed = self
+                    .claimed_amount
+                    .iter()
+                    .find(|c| c.denom == coin.denom)
+                    .map(|c| c.amount)
+                    .unwrap_or_default();
+                let delta = coin
+                    .amount
+                    .checked_sub(already_claimed)
+                    .unwrap_or_default();
+                if delta.is_zero() {
+                    None
+                } else {
+                    Some(Coin {
+                        denom: coin.denom.clone(),
+                        amount: delta,
+                    })
+                }
+            })
+            .collect()
+    }
+
+    pub fn add_funder(&mut self, address: Addr, amount: Uint128) {
+        match self.funders.iter_mut().find(|f| f.address == address) {
+            Some(funder) => funder.amount += amount,
+            None => self.funders.push(Funder { address, amount }),
+        }
+    }
+
+    pub fn total_funded(&self) -> Uint128 {
+        self.funders.iter().map(|f| f.amount).sum()
+    }
+}
+
+pub fn config(storage: &mut dyn cosmwasm_std::Storage) -> Singleton<State> {
+    singleton(storage, CONFIG_KEY)
+}
+
+pub fn config_read(storage: &dyn cosmwasm_std::Storage) -> ReadonlySingleton<State> {
+    singleton_read(storage, CONFIG_KEY)
+}